@@ -3,10 +3,14 @@ Rust Backend - Concurrent Actor Architecture
 Handles: Concurrent note processing, message passing, distributed state management
 */
 
-use actix_web::{web, App, HttpResponse, HttpServer, Result};
-use actix::{Actor, Context, Handler, Message, Addr, SyncArbiter};
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, Result};
+use actix::{
+    Actor, ActorContext, ActorFutureExt, AsyncContext, Context, ContextFutureSpawner, Handler,
+    Message, Addr, Recipient, ResponseFuture, SyncArbiter, WrapFuture,
+};
+use actix_web_actors::ws;
 use serde::{Deserialize, Serialize};
-use tokio_postgres::{NoTls, Client};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -15,10 +19,850 @@ use futures::future::join_all;
 // Database Configuration
 const DB_URL: &str = "host=localhost user=piano_user password=secure_password dbname=piano_db";
 
+// Default Postgres NOTIFY channel used for cross-process room fan-out. See
+// the `notify` module doc comment for the trigger that feeds it.
+const DEFAULT_NOTE_CHANNEL: &str = "note_events";
+
+// Deployment Configuration
+//
+// Picks the storage backend at startup so the crate can run without
+// Postgres at all, e.g. for local development or a desktop/offline build.
+mod config {
+    const SLED_PATH_DEFAULT: &str = "./piano_data";
+
+    pub enum StoreBackend {
+        Postgres,
+        Sled { path: String },
+    }
+
+    /// Reads `PIANO_STORE_BACKEND` ("postgres", the default, or "sled") and,
+    /// for sled, `PIANO_SLED_PATH` (default `./piano_data`).
+    pub fn store_backend_from_env() -> StoreBackend {
+        match std::env::var("PIANO_STORE_BACKEND").as_deref() {
+            Ok("sled") => StoreBackend::Sled {
+                path: std::env::var("PIANO_SLED_PATH").unwrap_or_else(|_| SLED_PATH_DEFAULT.to_string()),
+            },
+            _ => StoreBackend::Postgres,
+        }
+    }
+
+    /// Reads `PIANO_NOTE_CHANNEL` (default [`super::DEFAULT_NOTE_CHANNEL`])
+    /// and, for deployments that fan a single Postgres cluster out across
+    /// multiple independently-deployed rooms, `PIANO_ROOM_FILTER` — when set,
+    /// `NotifyActor` drops notifications for every other room.
+    pub fn notify_config_from_env() -> (String, Option<String>) {
+        let channel = std::env::var("PIANO_NOTE_CHANNEL").unwrap_or_else(|_| super::DEFAULT_NOTE_CHANNEL.to_string());
+        let room_filter = std::env::var("PIANO_ROOM_FILTER").ok();
+        (channel, room_filter)
+    }
+}
+
+// Storage Abstraction
+//
+// `DatabaseActor` and `NoteProcessorActor` talk to an `Arc<dyn Store>`
+// rather than a concrete Postgres client, so the same actor messages work
+// whether notes land in the pooled Postgres backend or the embedded sled
+// backend selected via `config::store_backend_from_env`.
+mod store {
+    use super::{NoteEvent, SongSaved};
+
+    #[async_trait::async_trait]
+    pub trait Store: Send + Sync {
+        async fn record_note(
+            &self,
+            room: &str,
+            user_id: &str,
+            key_number: i32,
+            velocity: f32,
+            timestamp: i64,
+        ) -> Result<(), String>;
+
+        async fn get_user_notes(&self, user_id: &str, limit: i64) -> Result<Vec<NoteEvent>, String>;
+
+        async fn save_song(&self, user_id: &str, song_name: &str, notes: &[i32]) -> Result<SongSaved, String>;
+    }
+}
+
+// Database Access Layer
+//
+// All pooled SQL lives here behind a cloneable `Db` handle backed by a bb8
+// pool, so the request-handling actors never touch a raw
+// `tokio_postgres::Client` directly and never serialize concurrent requests
+// behind a mutex. The one exception is `notify::NotifyActor`, which keeps its
+// own dedicated connection for `LISTEN`.
+mod db {
+    use super::jobs::JobPayload;
+    use super::{NoteEvent, SongSaved, DB_URL};
+    use bb8::Pool;
+    use bb8_postgres::PostgresConnectionManager;
+    use tokio_postgres::NoTls;
+
+    pub type PgPool = Pool<PostgresConnectionManager<NoTls>>;
+
+    /// A job claimed off the `jobs` table by a worker.
+    pub struct JobRow {
+        pub id: i64,
+        pub payload: JobPayload,
+        pub attempts: i32,
+    }
+
+    #[derive(Clone)]
+    pub struct Db {
+        pool: PgPool,
+    }
+
+    impl Db {
+        /// Connects a pool sized to roughly `num_cpus * 4`, matching the
+        /// actor pool's concurrency rather than a single shared connection.
+        pub async fn connect() -> Result<Self, String> {
+            let config: tokio_postgres::Config = DB_URL
+                .parse()
+                .map_err(|e| format!("Invalid database config: {}", e))?;
+            let manager = PostgresConnectionManager::new(config, NoTls);
+            let max_size = (num_cpus::get() * 4) as u32;
+
+            let pool = Pool::builder()
+                .max_size(max_size)
+                .build(manager)
+                .await
+                .map_err(|e| format!("Failed to build connection pool: {}", e))?;
+
+            Ok(Db { pool })
+        }
+
+        /// Inserts into `notes`, which carries an `AFTER INSERT` trigger
+        /// (`notes_notify`, running `notify_note_event`) that calls
+        /// `pg_notify(channel, row_to_json(NEW)::text)` so every process
+        /// listening on that channel observes the write — this insert is
+        /// the single source of truth for both persistence and fan-out.
+        pub async fn insert_note(
+            &self,
+            room: &str,
+            user_id: &str,
+            key_number: i32,
+            velocity: f32,
+            timestamp: i64,
+        ) -> Result<(), String> {
+            let conn = self
+                .pool
+                .get()
+                .await
+                .map_err(|e| format!("Failed to get pooled connection: {}", e))?;
+
+            conn.execute(
+                "INSERT INTO notes (room, user_id, key_number, velocity, timestamp) VALUES ($1, $2, $3, $4, $5)",
+                &[&room, &user_id, &key_number, &velocity, &timestamp],
+            )
+            .await
+            .map_err(|e| format!("Failed to insert note: {}", e))?;
+
+            Ok(())
+        }
+
+        /// `limit` is `i64` because Postgres infers an untyped `LIMIT $n`
+        /// placeholder as `int8`; binding an `i32` there fails with
+        /// `WrongType { postgres: Int8, rust: "i32" }` regardless of the SQL
+        /// text around it.
+        pub async fn select_user_notes(
+            &self,
+            user_id: &str,
+            limit: i64,
+        ) -> Result<Vec<NoteEvent>, String> {
+            let conn = self
+                .pool
+                .get()
+                .await
+                .map_err(|e| format!("Failed to get pooled connection: {}", e))?;
+
+            let rows = conn
+                .query(
+                    "SELECT key_number, velocity, timestamp FROM notes \
+                     WHERE user_id = $1 ORDER BY timestamp DESC LIMIT $2",
+                    &[&user_id, &limit],
+                )
+                .await
+                .map_err(|e| format!("Failed to query notes: {}", e))?;
+
+            Ok(rows
+                .into_iter()
+                .map(|row| NoteEvent {
+                    key_number: row.get(0),
+                    velocity: row.get(1),
+                    timestamp: row.get(2),
+                })
+                .collect())
+        }
+
+        pub async fn insert_song(
+            &self,
+            user_id: &str,
+            song_name: &str,
+            notes: &[i32],
+        ) -> Result<SongSaved, String> {
+            let conn = self
+                .pool
+                .get()
+                .await
+                .map_err(|e| format!("Failed to get pooled connection: {}", e))?;
+
+            let row = conn
+                .query_one(
+                    "INSERT INTO songs (user_id, song_name, notes) VALUES ($1, $2, $3) RETURNING id",
+                    &[&user_id, &song_name, &notes],
+                )
+                .await
+                .map_err(|e| format!("Failed to insert song: {}", e))?;
+
+            Ok(SongSaved {
+                song_id: row.get(0),
+                saved: true,
+            })
+        }
+
+        /// Enqueues a durable job row; `jobs::JobQueue` workers drain it
+        /// independently of the request that created it.
+        ///
+        /// The payload is serialized to JSON and stored in a plain `TEXT`
+        /// column rather than `jsonb`: binding a `String` against a `jsonb`
+        /// parameter fails regardless of an `::jsonb` cast in the SQL text,
+        /// since `String`'s `ToSql` impl only accepts text-family types, and
+        /// `serde_json::Value`'s `ToSql` impl lives behind tokio-postgres's
+        /// `with-serde_json-1` feature, which this crate doesn't enable.
+        pub async fn enqueue_job(&self, payload: &JobPayload) -> Result<i64, String> {
+            let conn = self
+                .pool
+                .get()
+                .await
+                .map_err(|e| format!("Failed to get pooled connection: {}", e))?;
+
+            let payload_json = serde_json::to_string(payload)
+                .map_err(|e| format!("Failed to serialize job payload: {}", e))?;
+
+            let row = conn
+                .query_one(
+                    "INSERT INTO jobs (payload, status) VALUES ($1, 'pending') RETURNING id",
+                    &[&payload_json],
+                )
+                .await
+                .map_err(|e| format!("Failed to enqueue job: {}", e))?;
+
+            Ok(row.get(0))
+        }
+
+        /// Atomically claims one pending job that's due (`not_before <= now()`)
+        /// for this worker, skipping rows already locked by another worker.
+        pub async fn claim_next_job(&self) -> Result<Option<JobRow>, String> {
+            let conn = self
+                .pool
+                .get()
+                .await
+                .map_err(|e| format!("Failed to get pooled connection: {}", e))?;
+
+            let row = conn
+                .query_opt(
+                    "UPDATE jobs SET status = 'processing' \
+                     WHERE id = ( \
+                         SELECT id FROM jobs WHERE status = 'pending' AND not_before <= now() \
+                         ORDER BY id FOR UPDATE SKIP LOCKED LIMIT 1 \
+                     ) \
+                     RETURNING id, payload, attempts",
+                    &[],
+                )
+                .await
+                .map_err(|e| format!("Failed to claim job: {}", e))?;
+
+            row.map(|row| {
+                let payload_json: String = row.get(1);
+                let payload = serde_json::from_str(&payload_json)
+                    .map_err(|e| format!("Corrupt job payload: {}", e))?;
+                Ok(JobRow {
+                    id: row.get(0),
+                    payload,
+                    attempts: row.get(2),
+                })
+            })
+            .transpose()
+        }
+
+        pub async fn complete_job(&self, id: i64) -> Result<(), String> {
+            let conn = self
+                .pool
+                .get()
+                .await
+                .map_err(|e| format!("Failed to get pooled connection: {}", e))?;
+
+            conn.execute("DELETE FROM jobs WHERE id = $1", &[&id])
+                .await
+                .map_err(|e| format!("Failed to complete job: {}", e))?;
+
+            Ok(())
+        }
+
+        /// Re-queues a failed attempt unless it has exhausted `max_attempts`,
+        /// in which case the job is parked as `failed` instead of retried
+        /// forever. `retry_after` pushes `not_before` out so the retry is
+        /// scheduled rather than immediately reclaimable, freeing the worker
+        /// that hit this failure to pick up other pending jobs right away
+        /// instead of sleeping on it.
+        pub async fn fail_job(
+            &self,
+            id: i64,
+            max_attempts: i32,
+            retry_after: std::time::Duration,
+            error: &str,
+        ) -> Result<(), String> {
+            let conn = self
+                .pool
+                .get()
+                .await
+                .map_err(|e| format!("Failed to get pooled connection: {}", e))?;
+
+            let retry_after_secs = retry_after.as_secs_f64();
+            conn.execute(
+                "UPDATE jobs SET \
+                     status = CASE WHEN attempts + 1 >= $2 THEN 'failed' ELSE 'pending' END, \
+                     attempts = attempts + 1, \
+                     last_error = $3, \
+                     not_before = now() + $4 * INTERVAL '1 second' \
+                 WHERE id = $1",
+                &[&id, &max_attempts, &error, &retry_after_secs],
+            )
+            .await
+            .map_err(|e| format!("Failed to record job failure: {}", e))?;
+
+            Ok(())
+        }
+
+        /// Resets jobs a crashed worker left `processing` back to `pending`
+        /// so a restarted worker pool picks them back up. Run once at
+        /// startup, before workers start claiming jobs.
+        pub async fn recover_stuck_jobs(&self) -> Result<u64, String> {
+            let conn = self
+                .pool
+                .get()
+                .await
+                .map_err(|e| format!("Failed to get pooled connection: {}", e))?;
+
+            conn.execute("UPDATE jobs SET status = 'pending' WHERE status = 'processing'", &[])
+                .await
+                .map_err(|e| format!("Failed to recover stuck jobs: {}", e))
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl super::store::Store for Db {
+        async fn record_note(
+            &self,
+            room: &str,
+            user_id: &str,
+            key_number: i32,
+            velocity: f32,
+            timestamp: i64,
+        ) -> Result<(), String> {
+            self.insert_note(room, user_id, key_number, velocity, timestamp).await
+        }
+
+        async fn get_user_notes(&self, user_id: &str, limit: i64) -> Result<Vec<NoteEvent>, String> {
+            self.select_user_notes(user_id, limit).await
+        }
+
+        async fn save_song(&self, user_id: &str, song_name: &str, notes: &[i32]) -> Result<SongSaved, String> {
+            self.insert_song(user_id, song_name, notes).await
+        }
+    }
+}
+
+// Embedded Sled Backend
+//
+// Implements `Store` against a local `sled` keyspace so the crate runs
+// without Postgres at all, for local development or an offline/desktop
+// deployment where standing up Postgres is overkill. Songs and notes are
+// serde_json-encoded under a `user_id`-prefixed key so `GetUserNotes` can use
+// sled's prefix scan instead of a query.
+mod sled_store {
+    use super::store::Store;
+    use super::{NoteEvent, SongSaved};
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    pub struct SledStore {
+        db: sled::Db,
+        next_song_id: AtomicI64,
+        next_note_seq: AtomicI64,
+    }
+
+    impl SledStore {
+        pub fn open(path: &str) -> Result<Self, String> {
+            let db = sled::open(path).map_err(|e| format!("Failed to open sled store at '{}': {}", path, e))?;
+            let next_song_id = Self::highest_song_id(&db) + 1;
+            Ok(SledStore {
+                db,
+                next_song_id: AtomicI64::new(next_song_id),
+                next_note_seq: AtomicI64::new(0),
+            })
+        }
+
+        /// Scans existing `song/<user_id>/<song_id>` keys for the highest
+        /// `song_id` already persisted, so a restart resumes the counter
+        /// instead of reusing ids and overwriting previously-saved songs.
+        fn highest_song_id(db: &sled::Db) -> i64 {
+            db.scan_prefix(b"song/")
+                .keys()
+                .filter_map(|k| k.ok())
+                .filter_map(|k| {
+                    std::str::from_utf8(&k)
+                        .ok()
+                        .and_then(|k| k.rsplit('/').next())
+                        .and_then(|id| id.parse::<i64>().ok())
+                })
+                .max()
+                .unwrap_or(0)
+        }
+
+        fn note_prefix(user_id: &str) -> String {
+            format!("note/{}/", user_id)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Store for SledStore {
+        async fn record_note(
+            &self,
+            _room: &str,
+            user_id: &str,
+            key_number: i32,
+            velocity: f32,
+            timestamp: i64,
+        ) -> Result<(), String> {
+            // Zero-padded so lexicographic key order matches timestamp order.
+            // The sequence suffix keeps two notes landing in the same
+            // millisecond (a chord, or two quick WS frames) from colliding
+            // on the same key and silently overwriting each other.
+            let seq = self.next_note_seq.fetch_add(1, Ordering::Relaxed);
+            let key = format!("{}{:020}-{:020}", Self::note_prefix(user_id), timestamp, seq);
+            let note = NoteEvent {
+                key_number,
+                velocity,
+                timestamp,
+            };
+            let bytes = serde_json::to_vec(&note).map_err(|e| format!("Failed to serialize note: {}", e))?;
+
+            self.db
+                .insert(key, bytes)
+                .map_err(|e| format!("sled insert failed: {}", e))?;
+
+            Ok(())
+        }
+
+        async fn get_user_notes(&self, user_id: &str, limit: i64) -> Result<Vec<NoteEvent>, String> {
+            let mut notes: Vec<NoteEvent> = self
+                .db
+                .scan_prefix(Self::note_prefix(user_id).as_bytes())
+                .values()
+                .filter_map(|v| v.ok())
+                .filter_map(|v| serde_json::from_slice(&v).ok())
+                .collect();
+
+            notes.sort_by_key(|n| std::cmp::Reverse(n.timestamp));
+            notes.truncate(limit.max(0) as usize);
+
+            Ok(notes)
+        }
+
+        async fn save_song(&self, user_id: &str, song_name: &str, notes: &[i32]) -> Result<SongSaved, String> {
+            let song_id = self.next_song_id.fetch_add(1, Ordering::Relaxed);
+            let key = format!("song/{}/{}", user_id, song_id);
+            let bytes = serde_json::to_vec(&serde_json::json!({
+                "song_name": song_name,
+                "notes": notes,
+            }))
+            .map_err(|e| format!("Failed to serialize song: {}", e))?;
+
+            self.db
+                .insert(key, bytes)
+                .map_err(|e| format!("sled insert failed: {}", e))?;
+
+            Ok(SongSaved {
+                song_id: song_id as i32,
+                saved: true,
+            })
+        }
+    }
+}
+
+// User Notes Cache
+//
+// `GetUserNotes` used to hit Postgres on every call. A short-lived TTL cache
+// keyed by `user_id` absorbs repeat reads, and a background task rehydrates
+// hot entries before they expire so a cache miss is the exception rather
+// than the steady state.
+mod cache {
+    use super::NoteEvent;
+    use async_rwlock::RwLock;
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use ttl_cache::TtlCache;
+
+    const CAPACITY: usize = 1024;
+    pub const NOTES_TTL: Duration = Duration::from_secs(5 * 60);
+
+    #[derive(Clone, Serialize, Deserialize)]
+    pub enum MaybeCached<T> {
+        Cached(T),
+        Fresh(T),
+    }
+
+    #[derive(Clone)]
+    pub struct UserCache {
+        notes: Arc<RwLock<TtlCache<String, Vec<NoteEvent>>>>,
+        // Last time each user_id was read, so `hot_users` can expire entries
+        // alongside `NOTES_TTL` instead of growing forever: without this, every
+        // user who ever called `GetUserNotes` once gets rehydrated from the
+        // store on every tick for the life of the process.
+        hot_users: Arc<Mutex<HashMap<String, Instant>>>,
+    }
+
+    impl UserCache {
+        pub fn new() -> Self {
+            UserCache {
+                notes: Arc::new(RwLock::new(TtlCache::new(CAPACITY))),
+                hot_users: Arc::new(Mutex::new(HashMap::new())),
+            }
+        }
+
+        pub async fn get(&self, user_id: &str) -> Option<Vec<NoteEvent>> {
+            self.hot_users.lock().unwrap().insert(user_id.to_string(), Instant::now());
+            self.notes.read().await.get(user_id).cloned()
+        }
+
+        pub async fn put(&self, user_id: String, notes: Vec<NoteEvent>) {
+            self.notes.write().await.insert(user_id, notes, NOTES_TTL);
+        }
+
+        pub async fn invalidate(&self, user_id: &str) {
+            self.notes.write().await.remove(user_id);
+        }
+
+        /// `user_id`s read within the last `NOTES_TTL`; candidates for the
+        /// background rehydrate task. Stale entries are pruned here so a
+        /// user who stops reading eventually drops out instead of being
+        /// rehydrated forever.
+        pub fn hot_users(&self) -> Vec<String> {
+            let mut hot_users = self.hot_users.lock().unwrap();
+            hot_users.retain(|_, last_read| last_read.elapsed() < NOTES_TTL);
+            hot_users.keys().cloned().collect()
+        }
+    }
+}
+
+// Durable Job Queue
+//
+// `ProcessNote` and `SaveSong` used to block the request on a pooled
+// connection. Now they enqueue a durable `jobs` row and return immediately;
+// a pool of workers drains the queue with retry/backoff, and job state
+// (pending/processing/failed) lives in Postgres so a crashed worker's jobs
+// are re-claimed on restart rather than lost. Provisioning runs, once:
+//
+//   CREATE TABLE jobs (
+//       id BIGSERIAL PRIMARY KEY,
+//       payload TEXT NOT NULL,
+//       status TEXT NOT NULL DEFAULT 'pending',
+//       attempts INT NOT NULL DEFAULT 0,
+//       last_error TEXT,
+//       not_before TIMESTAMPTZ NOT NULL DEFAULT now(),
+//       created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+//   );
+mod jobs {
+    use super::{cache, db};
+    use serde::{Deserialize, Serialize};
+    use std::time::Duration;
+
+    const WORKER_COUNT: usize = 4;
+    const MAX_ATTEMPTS: i32 = 5;
+    const BASE_BACKOFF: Duration = Duration::from_millis(200);
+    const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+    #[derive(Clone, Serialize, Deserialize)]
+    #[serde(tag = "kind")]
+    pub enum JobPayload {
+        PersistNote {
+            room: String,
+            user_id: String,
+            key_number: i32,
+            velocity: f32,
+            timestamp: i64,
+        },
+        PersistSong {
+            user_id: String,
+            song_name: String,
+            notes: Vec<i32>,
+        },
+    }
+
+    #[derive(Clone)]
+    pub struct JobQueue {
+        db: db::Db,
+        cache: cache::UserCache,
+    }
+
+    impl JobQueue {
+        pub fn new(db: db::Db, cache: cache::UserCache) -> Self {
+            JobQueue { db, cache }
+        }
+
+        pub async fn enqueue(&self, payload: JobPayload) -> Result<i64, String> {
+            self.db.enqueue_job(&payload).await
+        }
+
+        /// Resets jobs orphaned by a previous crash. Call once at startup
+        /// before `spawn_workers`.
+        pub async fn recover(&self) -> Result<(), String> {
+            let recovered = self.db.recover_stuck_jobs().await?;
+            if recovered > 0 {
+                println!("✓ JobQueue recovered {} stuck job(s)", recovered);
+            }
+            Ok(())
+        }
+
+        /// Spawns `WORKER_COUNT` tasks that poll for pending jobs and drain
+        /// them, backing off on transient failures instead of busy-looping.
+        pub fn spawn_workers(&self) {
+            for worker_id in 0..WORKER_COUNT {
+                let db = self.db.clone();
+                let cache = self.cache.clone();
+                tokio::spawn(async move {
+                    loop {
+                        match db.claim_next_job().await {
+                            Ok(Some(job)) => {
+                                if let Err(e) = Self::run(&db, &cache, &job).await {
+                                    eprintln!("job worker {} failed job {}: {}", worker_id, job.id, e);
+                                }
+                            }
+                            Ok(None) => tokio::time::sleep(POLL_INTERVAL).await,
+                            Err(e) => {
+                                eprintln!("job worker {} failed to poll queue: {}", worker_id, e);
+                                tokio::time::sleep(POLL_INTERVAL).await;
+                            }
+                        }
+                    }
+                });
+            }
+        }
+
+        fn user_id(payload: &JobPayload) -> &str {
+            match payload {
+                JobPayload::PersistNote { user_id, .. } => user_id,
+                JobPayload::PersistSong { user_id, .. } => user_id,
+            }
+        }
+
+        async fn run(db: &db::Db, cache: &cache::UserCache, job: &db::JobRow) -> Result<(), String> {
+            let result = match &job.payload {
+                JobPayload::PersistNote {
+                    room,
+                    user_id,
+                    key_number,
+                    velocity,
+                    timestamp,
+                } => db.insert_note(room, user_id, *key_number, *velocity, *timestamp).await,
+                JobPayload::PersistSong {
+                    user_id,
+                    song_name,
+                    notes,
+                } => db.insert_song(user_id, song_name, notes).await.map(|_| ()),
+            };
+
+            match result {
+                // Only now has the note/song actually landed in Postgres, so
+                // only now is it safe to drop the cached (now-stale) list —
+                // invalidating at enqueue time let a `GetUserNotes` racing
+                // the worker re-cache the pre-write list for the full TTL.
+                Ok(()) => {
+                    cache.invalidate(Self::user_id(&job.payload)).await;
+                    db.complete_job(job.id).await
+                }
+                Err(e) => {
+                    // Schedule the retry via `not_before` instead of sleeping
+                    // the worker on it, so a failing job doesn't tie up one
+                    // of only `WORKER_COUNT` workers for the backoff
+                    // duration — the worker is freed to claim other pending
+                    // jobs immediately.
+                    let backoff = BASE_BACKOFF * 2u32.pow(job.attempts.clamp(0, 5) as u32);
+                    db.fail_job(job.id, MAX_ATTEMPTS, backoff, &e).await?;
+                    Err(e)
+                }
+            }
+        }
+    }
+}
+
+// Real-time Collaboration
+//
+// Postgres fans out every committed note to every process via LISTEN/NOTIFY,
+// which is what keeps collaborative "rooms" in sync without routing writes
+// through a single node. Provisioning runs, once:
+//
+//   ALTER TABLE notes ADD COLUMN room TEXT NOT NULL DEFAULT 'lobby';
+//
+//   CREATE FUNCTION notify_note_event() RETURNS trigger AS $$
+//   BEGIN
+//       PERFORM pg_notify(TG_ARGV[0], row_to_json(NEW)::text);
+//       RETURN NEW;
+//   END;
+//   $$ LANGUAGE plpgsql;
+//
+//   CREATE TRIGGER notes_notify AFTER INSERT ON notes
+//       FOR EACH ROW EXECUTE FUNCTION notify_note_event('note_events');
+mod notify {
+    use super::{Actor, AsyncContext, Context, Message, Recipient, DB_URL};
+    use futures::channel::mpsc;
+    use futures::future::poll_fn;
+    use futures::StreamExt;
+    use serde::{Deserialize, Serialize};
+    use std::time::Duration;
+    use tokio_postgres::{AsyncMessage, NoTls};
+
+    // Reconnect backoff for the LISTEN session: a transient network blip or a
+    // Postgres restart must not permanently kill cross-process fan-out, so a
+    // dropped connection reconnects instead of letting the task exit.
+    const BASE_RECONNECT_BACKOFF: Duration = Duration::from_millis(200);
+    const MAX_RECONNECT_ATTEMPT: u32 = 7; // 200ms * 2^7 = 25.6s ceiling
+
+    #[derive(Clone, Serialize, Deserialize)]
+    pub struct NoteEventPayload {
+        pub room: String,
+        pub user_id: String,
+        pub key_number: i32,
+        pub velocity: f32,
+        pub timestamp: i64,
+    }
+
+    #[derive(Message, Clone, Serialize, Deserialize)]
+    #[rtype(result = "()")]
+    pub struct NoteBroadcast {
+        pub room: String,
+        pub payload: NoteEventPayload,
+    }
+
+    /// Holds its own long-lived `tokio_postgres` connection rather than one
+    /// borrowed from the bb8 pool, since a `LISTEN` session must stay open
+    /// for the actor's lifetime instead of being returned after a query.
+    pub struct NotifyActor {
+        channel: String,
+        room_filter: Option<String>,
+        subscriber: Recipient<NoteBroadcast>,
+    }
+
+    impl NotifyActor {
+        pub fn new(
+            channel: impl Into<String>,
+            room_filter: Option<String>,
+            subscriber: Recipient<NoteBroadcast>,
+        ) -> Self {
+            NotifyActor {
+                channel: channel.into(),
+                room_filter,
+                subscriber,
+            }
+        }
+
+        /// Sleeps the backoff for `attempt`, then bumps it, so the caller's
+        /// next failure waits longer — reset `attempt` to 0 once a connection
+        /// is established so a long-lived session isn't penalized by an old
+        /// outage.
+        async fn backoff(attempt: &mut u32) {
+            let delay = BASE_RECONNECT_BACKOFF * 2u32.pow((*attempt).min(MAX_RECONNECT_ATTEMPT));
+            *attempt += 1;
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    impl Actor for NotifyActor {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            println!("✓ NotifyActor listening on '{}'", self.channel);
+
+            let channel = self.channel.clone();
+            let room_filter = self.room_filter.clone();
+            let subscriber = self.subscriber.clone();
+
+            let fut = async move {
+                let mut attempt: u32 = 0;
+
+                // Reconnect loop: a dropped connection or a failed LISTEN
+                // retries with backoff instead of letting the task exit and
+                // permanently killing cross-process fan-out.
+                loop {
+                    let (client, mut connection) = match tokio_postgres::connect(DB_URL, NoTls).await {
+                        Ok(pair) => pair,
+                        Err(e) => {
+                            eprintln!("NotifyActor failed to connect: {}", e);
+                            Self::backoff(&mut attempt).await;
+                            continue;
+                        }
+                    };
+
+                    // `batch_execute` below can only resolve once something is
+                    // actually polling `connection` to drive the socket I/O, so
+                    // that polling has to start running on its own task *before*
+                    // we await LISTEN rather than after — otherwise the two
+                    // futures wait on each other forever.
+                    let (tx, mut rx) = mpsc::unbounded();
+                    tokio::spawn(async move {
+                        while let Some(message) = poll_fn(|cx| connection.poll_message(cx)).await {
+                            if tx.unbounded_send(message).is_err() {
+                                break;
+                            }
+                        }
+                    });
+
+                    if let Err(e) = client.batch_execute(&format!("LISTEN {};", channel)).await {
+                        eprintln!("NotifyActor failed to LISTEN on '{}': {}", channel, e);
+                        Self::backoff(&mut attempt).await;
+                        continue;
+                    }
+
+                    println!("✓ NotifyActor connected, listening on '{}'", channel);
+                    attempt = 0;
+
+                    while let Some(message) = rx.next().await {
+                        match message {
+                            Ok(AsyncMessage::Notification(note)) => {
+                                match serde_json::from_str::<NoteEventPayload>(note.payload()) {
+                                    Ok(payload) => {
+                                        if room_filter.as_deref().is_none_or(|r| r == payload.room) {
+                                            let room = payload.room.clone();
+                                            subscriber.do_send(NoteBroadcast { room, payload });
+                                        }
+                                    }
+                                    Err(e) => eprintln!("NotifyActor: malformed note_events payload: {}", e),
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                eprintln!("NotifyActor connection error: {}", e);
+                                break;
+                            }
+                        }
+                    }
+
+                    eprintln!("NotifyActor lost connection on '{}', reconnecting...", channel);
+                    Self::backoff(&mut attempt).await;
+                }
+            };
+
+            ctx.spawn(actix::fut::wrap_future(fut));
+        }
+    }
+}
+
 // Message Types for Actor System
 #[derive(Message, Clone, Serialize, Deserialize)]
 #[rtype(result = "Result<NoteProcessed, String>")]
 pub struct ProcessNote {
+    room: String,
     user_id: String,
     key_number: i32,
     velocity: f32,
@@ -26,10 +870,10 @@ pub struct ProcessNote {
 }
 
 #[derive(Message, Clone, Serialize, Deserialize)]
-#[rtype(result = "Result<Vec<NoteEvent>, String>")]
+#[rtype(result = "Result<cache::MaybeCached<Vec<NoteEvent>>, String>")]
 pub struct GetUserNotes {
     user_id: String,
-    limit: i32,
+    limit: i64,
 }
 
 #[derive(Message, Clone, Serialize, Deserialize)]
@@ -65,15 +909,24 @@ pub struct SongSaved {
 // Note Processor Actor
 pub struct NoteProcessorActor {
     id: usize,
-    db_client: Arc<Mutex<Option<Client>>>,
+    store: Arc<dyn store::Store>,
+    jobs: Option<jobs::JobQueue>,
+    cache: cache::UserCache,
     processed_count: u64,
 }
 
 impl NoteProcessorActor {
-    pub fn new(id: usize, db_client: Arc<Mutex<Option<Client>>>) -> Self {
+    pub fn new(
+        id: usize,
+        store: Arc<dyn store::Store>,
+        jobs: Option<jobs::JobQueue>,
+        cache: cache::UserCache,
+    ) -> Self {
         NoteProcessorActor {
             id,
-            db_client,
+            store,
+            jobs,
+            cache,
             processed_count: 0,
         }
     }
@@ -81,118 +934,383 @@ impl NoteProcessorActor {
 
 impl Actor for NoteProcessorActor {
     type Context = Context<Self>;
-    
+
     fn started(&mut self, _ctx: &mut Self::Context) {
         println!("✓ NoteProcessorActor {} started", self.id);
     }
 }
 
 impl Handler<ProcessNote> for NoteProcessorActor {
-    type Result = Result<NoteProcessed, String>;
-    
+    type Result = ResponseFuture<Result<NoteProcessed, String>>;
+
     fn handle(&mut self, msg: ProcessNote, _ctx: &mut Self::Context) -> Self::Result {
-        let start = SystemTime::now();
-        
-        // Simulate concurrent processing
-        std::thread::sleep(std::time::Duration::from_micros(100));
-        
-        // Process the note (could include complex audio processing)
-        let frequency = 440.0 * 2.0_f32.powf((msg.key_number - 69) as f32 / 12.0);
-        
-        // Store in database (async would be better in production)
-        if let Ok(mut guard) = self.db_client.lock() {
-            if let Some(client) = guard.as_mut() {
-                // Note: In real async code, we'd use tokio::spawn
-                // This is simplified for demonstration
-            }
-        }
-        
+        let store = Arc::clone(&self.store);
+        let jobs = self.jobs.clone();
+        let cache = self.cache.clone();
+        let worker_id = self.id;
         self.processed_count += 1;
-        
-        let processing_time = start.elapsed()
-            .map(|d| d.as_micros())
-            .unwrap_or(0);
-        
-        Ok(NoteProcessed {
-            key_number: msg.key_number,
-            processed: true,
-            worker_id: self.id,
-            processing_time_us: processing_time,
+
+        Box::pin(async move {
+            let start = SystemTime::now();
+
+            // Could feed into real audio processing; kept for parity with
+            // the key_number -> pitch mapping the frontend expects.
+            let _frequency = 440.0 * 2.0_f32.powf((msg.key_number - 69) as f32 / 12.0);
+
+            match jobs {
+                // Postgres: the durable insert happens off a job worker, not
+                // this request, so the cache isn't invalidated here either —
+                // `jobs::run` does that once the insert actually commits,
+                // rather than letting a racing `GetUserNotes` re-cache the
+                // pre-write list for the cache's full TTL.
+                Some(jobs) => {
+                    jobs.enqueue(jobs::JobPayload::PersistNote {
+                        room: msg.room.clone(),
+                        user_id: msg.user_id.clone(),
+                        key_number: msg.key_number,
+                        velocity: msg.velocity,
+                        timestamp: msg.timestamp,
+                    })
+                    .await?;
+                }
+                // Sled (or any backend without a job queue): there's no
+                // network round trip to hide, so write straight through and
+                // invalidate immediately.
+                None => {
+                    store
+                        .record_note(&msg.room, &msg.user_id, msg.key_number, msg.velocity, msg.timestamp)
+                        .await?;
+                    cache.invalidate(&msg.user_id).await;
+                }
+            }
+
+            let processing_time = start.elapsed().map(|d| d.as_micros()).unwrap_or(0);
+
+            Ok(NoteProcessed {
+                key_number: msg.key_number,
+                processed: true,
+                worker_id,
+                processing_time_us: processing_time,
+            })
         })
     }
 }
 
+// How often the background rehydrate task refreshes hot cache entries. Kept
+// comfortably inside `cache::NOTES_TTL` so a hot user's notes are refreshed
+// before they ever lapse.
+const REHYDRATE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+const REHYDRATE_NOTES_LIMIT: i64 = 50;
+
+// Negative, monotonically decreasing ids hand out an obviously-provisional
+// `song_id` before the job queue has actually persisted the song.
+static NEXT_PROVISIONAL_SONG_ID: AtomicI64 = AtomicI64::new(-1);
+
 // Database Actor
 pub struct DatabaseActor {
-    client: Option<Client>,
+    store: Arc<dyn store::Store>,
+    cache: cache::UserCache,
+    jobs: Option<jobs::JobQueue>,
 }
 
 impl DatabaseActor {
-    pub fn new() -> Self {
-        DatabaseActor { client: None }
-    }
-    
-    pub async fn connect(&mut self) -> Result<(), String> {
-        match tokio_postgres::connect(DB_URL, NoTls).await {
-            Ok((client, connection)) => {
-                tokio::spawn(async move {
-                    if let Err(e) = connection.await {
-                        eprintln!("Database connection error: {}", e);
-                    }
-                });
-                self.client = Some(client);
-                Ok(())
-            }
-            Err(e) => Err(format!("Failed to connect to database: {}", e)),
-        }
+    pub fn new(store: Arc<dyn store::Store>, cache: cache::UserCache, jobs: Option<jobs::JobQueue>) -> Self {
+        DatabaseActor { store, cache, jobs }
     }
 }
 
 impl Actor for DatabaseActor {
     type Context = Context<Self>;
-    
-    fn started(&mut self, ctx: &mut Self::Context) {
+
+    fn started(&mut self, _ctx: &mut Self::Context) {
         println!("✓ DatabaseActor started");
-        
-        let fut = async {
-            // In production, properly handle the connection
-        };
-        
-        ctx.spawn(actix::fut::wrap_future(fut));
+
+        let store = Arc::clone(&self.store);
+        let cache = self.cache.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REHYDRATE_INTERVAL);
+            loop {
+                interval.tick().await;
+                for user_id in cache.hot_users() {
+                    match store.get_user_notes(&user_id, REHYDRATE_NOTES_LIMIT).await {
+                        Ok(notes) => cache.put(user_id, notes).await,
+                        Err(e) => eprintln!("rehydrate failed for '{}': {}", user_id, e),
+                    }
+                }
+            }
+        });
     }
 }
 
 impl Handler<GetUserNotes> for DatabaseActor {
-    type Result = Result<Vec<NoteEvent>, String>;
-    
+    type Result = ResponseFuture<Result<cache::MaybeCached<Vec<NoteEvent>>, String>>;
+
     fn handle(&mut self, msg: GetUserNotes, _ctx: &mut Self::Context) -> Self::Result {
-        // Simulate database query
-        // In production, this would use async/await with the client
-        Ok(vec![
-            NoteEvent {
-                key_number: 60,
-                velocity: 0.8,
-                timestamp: SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as i64,
+        let store = Arc::clone(&self.store);
+        let cache = self.cache.clone();
+
+        Box::pin(async move {
+            if let Some(notes) = cache.get(&msg.user_id).await {
+                return Ok(cache::MaybeCached::Cached(notes));
             }
-        ])
+
+            let notes = store.get_user_notes(&msg.user_id, msg.limit).await?;
+            cache.put(msg.user_id, notes.clone()).await;
+            Ok(cache::MaybeCached::Fresh(notes))
+        })
     }
 }
 
 impl Handler<SaveSong> for DatabaseActor {
-    type Result = Result<SongSaved, String>;
-    
+    type Result = ResponseFuture<Result<SongSaved, String>>;
+
+    /// On Postgres, enqueues the real insert and replies with a provisional
+    /// id right away; a job worker performs the pooled-connection write
+    /// separately, retrying transient failures instead of holding this
+    /// request open. Backends without a job queue (sled) write through
+    /// immediately and return the real id.
     fn handle(&mut self, msg: SaveSong, _ctx: &mut Self::Context) -> Self::Result {
-        // Simulate database insert
-        Ok(SongSaved {
-            song_id: 1,
-            saved: true,
+        let store = Arc::clone(&self.store);
+        let jobs = self.jobs.clone();
+
+        Box::pin(async move {
+            match jobs {
+                Some(jobs) => {
+                    let provisional_song_id = NEXT_PROVISIONAL_SONG_ID.fetch_sub(1, Ordering::Relaxed) as i32;
+
+                    jobs.enqueue(jobs::JobPayload::PersistSong {
+                        user_id: msg.user_id,
+                        song_name: msg.song_name,
+                        notes: msg.notes,
+                    })
+                    .await?;
+
+                    Ok(SongSaved {
+                        song_id: provisional_song_id,
+                        saved: false,
+                    })
+                }
+                None => store.save_song(&msg.user_id, &msg.song_name, &msg.notes).await,
+            }
         })
     }
 }
 
+// Session Registry
+//
+// Tracks every connected WebSocket session, keyed by room and then by a
+// per-session id, so a broadcasted note can be fanned out to everyone
+// sharing that room without touching sessions in any other room.
+pub type SessionId = u64;
+
+#[derive(Message)]
+#[rtype(result = "SessionId")]
+pub struct Connect {
+    pub room: String,
+    pub addr: Recipient<notify::NoteBroadcast>,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Disconnect {
+    pub room: String,
+    pub id: SessionId,
+}
+
+pub struct SessionRegistry {
+    rooms: HashMap<String, HashMap<SessionId, Recipient<notify::NoteBroadcast>>>,
+    next_id: AtomicU64,
+}
+
+impl SessionRegistry {
+    pub fn new() -> Self {
+        SessionRegistry {
+            rooms: HashMap::new(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+}
+
+impl Default for SessionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Actor for SessionRegistry {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _ctx: &mut Self::Context) {
+        println!("✓ SessionRegistry started");
+    }
+}
+
+impl Handler<Connect> for SessionRegistry {
+    type Result = SessionId;
+
+    fn handle(&mut self, msg: Connect, _ctx: &mut Self::Context) -> Self::Result {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.rooms.entry(msg.room).or_default().insert(id, msg.addr);
+        id
+    }
+}
+
+impl Handler<Disconnect> for SessionRegistry {
+    type Result = ();
+
+    fn handle(&mut self, msg: Disconnect, _ctx: &mut Self::Context) -> Self::Result {
+        if let Some(sessions) = self.rooms.get_mut(&msg.room) {
+            sessions.remove(&msg.id);
+        }
+    }
+}
+
+/// Feeds Postgres NOTIFY events (see `notify::NotifyActor`) straight into the
+/// registry so every process observes room traffic, even notes inserted by a
+/// sibling node rather than this one.
+impl Handler<notify::NoteBroadcast> for SessionRegistry {
+    type Result = ();
+
+    fn handle(&mut self, msg: notify::NoteBroadcast, _ctx: &mut Self::Context) -> Self::Result {
+        if let Some(sessions) = self.rooms.get(&msg.room) {
+            for addr in sessions.values() {
+                addr.do_send(msg.clone());
+            }
+        }
+    }
+}
+
+// WebSocket Session Actor
+//
+// One instance per connected player. Key presses arriving over the socket
+// are routed through `ActorPoolManager::get_next_worker()` exactly like
+// `process_note_concurrent`, and both the resulting `NoteProcessed` and any
+// `NoteBroadcast`s from other players in the room are pushed back down the
+// same socket.
+pub struct WsSession {
+    id: SessionId,
+    room: String,
+    user_id: String,
+    pool: Arc<ActorPoolManager>,
+    registry: Addr<SessionRegistry>,
+}
+
+impl WsSession {
+    pub fn new(room: String, user_id: String, pool: Arc<ActorPoolManager>, registry: Addr<SessionRegistry>) -> Self {
+        WsSession {
+            id: 0,
+            room,
+            user_id,
+            pool,
+            registry,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct WsKeyPress {
+    key_number: i32,
+    velocity: Option<f32>,
+}
+
+impl Actor for WsSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let connect = Connect {
+            room: self.room.clone(),
+            addr: ctx.address().recipient(),
+        };
+
+        self.registry
+            .send(connect)
+            .into_actor(self)
+            .then(|id, act, _ctx| {
+                if let Ok(id) = id {
+                    act.id = id;
+                }
+                actix::fut::ready(())
+            })
+            .wait(ctx);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        self.registry.do_send(Disconnect {
+            room: self.room.clone(),
+            id: self.id,
+        });
+    }
+}
+
+impl Handler<notify::NoteBroadcast> for WsSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: notify::NoteBroadcast, ctx: &mut Self::Context) -> Self::Result {
+        if let Ok(json) = serde_json::to_string(&msg.payload) {
+            ctx.text(json);
+        }
+    }
+}
+
+impl actix::StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
+    fn handle(&mut self, item: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        let msg = match item {
+            Ok(msg) => msg,
+            Err(_) => {
+                ctx.stop();
+                return;
+            }
+        };
+
+        match msg {
+            ws::Message::Ping(bytes) => ctx.pong(&bytes),
+            ws::Message::Text(text) => {
+                let key_press: WsKeyPress = match serde_json::from_str(&text) {
+                    Ok(kp) => kp,
+                    Err(e) => {
+                        ctx.text(serde_json::json!({ "error": format!("bad key press: {}", e) }).to_string());
+                        return;
+                    }
+                };
+
+                let msg = ProcessNote {
+                    room: self.room.clone(),
+                    user_id: self.user_id.clone(),
+                    key_number: key_press.key_number,
+                    velocity: key_press.velocity.unwrap_or(0.8),
+                    timestamp: SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_millis() as i64,
+                };
+
+                let worker = self.pool.get_next_worker();
+
+                ctx.spawn(actix::fut::wrap_future(async move { worker.send(msg).await }).map(
+                    |result, _act, ctx: &mut ws::WebsocketContext<Self>| match result {
+                        Ok(Ok(processed)) => {
+                            if let Ok(json) = serde_json::to_string(&processed) {
+                                ctx.text(json);
+                            }
+                        }
+                        Ok(Err(e)) => ctx.text(serde_json::json!({ "error": e }).to_string()),
+                        Err(e) => ctx.text(
+                            serde_json::json!({ "error": format!("Actor communication error: {}", e) })
+                                .to_string(),
+                        ),
+                    },
+                ));
+            }
+            ws::Message::Close(reason) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            _ => {}
+        }
+    }
+}
+
 // Actor Pool Manager
 pub struct ActorPoolManager {
     note_processors: Vec<Addr<NoteProcessorActor>>,
@@ -201,31 +1319,44 @@ pub struct ActorPoolManager {
 }
 
 impl ActorPoolManager {
-    pub fn new(pool_size: usize) -> Self {
-        let db_client = Arc::new(Mutex::new(None));
-        
+    pub async fn new(pool_size: usize) -> Result<Self, String> {
+        let user_cache = cache::UserCache::new();
+
+        let (store, job_queue): (Arc<dyn store::Store>, Option<jobs::JobQueue>) =
+            match config::store_backend_from_env() {
+                config::StoreBackend::Postgres => {
+                    let db = db::Db::connect().await?;
+                    let job_queue = jobs::JobQueue::new(db.clone(), user_cache.clone());
+                    job_queue.recover().await?;
+                    job_queue.spawn_workers();
+                    (Arc::new(db), Some(job_queue))
+                }
+                config::StoreBackend::Sled { path } => {
+                    println!("✓ Running offline against embedded sled store at '{}'", path);
+                    (Arc::new(sled_store::SledStore::open(&path)?), None)
+                }
+            };
+
         let note_processors: Vec<Addr<NoteProcessorActor>> = (0..pool_size)
-            .map(|i| {
-                NoteProcessorActor::new(i, Arc::clone(&db_client)).start()
-            })
+            .map(|i| NoteProcessorActor::new(i, Arc::clone(&store), job_queue.clone(), user_cache.clone()).start())
             .collect();
-        
-        let database_actor = DatabaseActor::new().start();
-        
-        ActorPoolManager {
+
+        let database_actor = DatabaseActor::new(store, user_cache, job_queue).start();
+
+        Ok(ActorPoolManager {
             note_processors,
             database_actor,
             current_worker: Arc::new(Mutex::new(0)),
-        }
+        })
     }
-    
+
     pub fn get_next_worker(&self) -> Addr<NoteProcessorActor> {
         let mut current = self.current_worker.lock().unwrap();
         let worker = self.note_processors[*current].clone();
         *current = (*current + 1) % self.note_processors.len();
         worker
     }
-    
+
     pub fn get_database_actor(&self) -> Addr<DatabaseActor> {
         self.database_actor.clone()
     }
@@ -238,6 +1369,7 @@ struct NoteRequest {
     key_number: i32,
     velocity: Option<f32>,
     timestamp: Option<i64>,
+    room: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -258,6 +1390,14 @@ struct SongRequest {
     notes: Vec<i32>,
 }
 
+const DEFAULT_USER_NOTES_LIMIT: i64 = 50;
+
+#[derive(Deserialize)]
+struct UserNotesQuery {
+    user_id: String,
+    limit: Option<i64>,
+}
+
 #[derive(Serialize)]
 struct HealthResponse {
     status: String,
@@ -278,16 +1418,17 @@ async fn process_note_concurrent(
             .unwrap()
             .as_millis() as i64
     });
-    
+
     let msg = ProcessNote {
+        room: note.room.clone().unwrap_or_else(|| "lobby".to_string()),
         user_id: note.user_id.clone(),
         key_number: note.key_number,
         velocity: note.velocity.unwrap_or(0.8),
         timestamp,
     };
-    
+
     let worker = pool.get_next_worker();
-    
+
     match worker.send(msg).await {
         Ok(Ok(result)) => {
             let response = NoteResponse {
@@ -319,9 +1460,9 @@ async fn save_song(
         song_name: song.song_name.clone(),
         notes: song.notes.clone(),
     };
-    
+
     let db_actor = pool.get_database_actor();
-    
+
     match db_actor.send(msg).await {
         Ok(Ok(result)) => Ok(HttpResponse::Ok().json(serde_json::json!({
             "song_id": result.song_id,
@@ -337,6 +1478,58 @@ async fn save_song(
     }
 }
 
+async fn get_user_notes(
+    pool: web::Data<Arc<ActorPoolManager>>,
+    query: web::Query<UserNotesQuery>,
+) -> Result<HttpResponse> {
+    let msg = GetUserNotes {
+        user_id: query.user_id.clone(),
+        limit: query.limit.unwrap_or(DEFAULT_USER_NOTES_LIMIT),
+    };
+
+    let db_actor = pool.get_database_actor();
+
+    match db_actor.send(msg).await {
+        Ok(Ok(cache::MaybeCached::Cached(notes))) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "notes": notes,
+            "cached": true,
+        }))),
+        Ok(Ok(cache::MaybeCached::Fresh(notes))) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "notes": notes,
+            "cached": false,
+        }))),
+        Ok(Err(e)) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": e
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Actor communication error: {}", e)
+        }))),
+    }
+}
+
+#[derive(Deserialize)]
+struct WsConnectQuery {
+    user_id: String,
+    room: Option<String>,
+}
+
+async fn ws_index(
+    req: HttpRequest,
+    stream: web::Payload,
+    query: web::Query<WsConnectQuery>,
+    pool: web::Data<Arc<ActorPoolManager>>,
+    registry: web::Data<Addr<SessionRegistry>>,
+) -> Result<HttpResponse> {
+    let session = WsSession::new(
+        query.room.clone().unwrap_or_else(|| "lobby".to_string()),
+        query.user_id.clone(),
+        Arc::clone(&pool),
+        registry.get_ref().clone(),
+    );
+
+    ws::start(session, &req, stream)
+}
+
 async fn health_check(pool: web::Data<Arc<ActorPoolManager>>) -> Result<HttpResponse> {
     let response = HealthResponse {
         status: "healthy".to_string(),
@@ -357,19 +1550,40 @@ async fn health_check(pool: web::Data<Arc<ActorPoolManager>>) -> Result<HttpResp
 async fn main() -> std::io::Result<()> {
     println!("🎹 Rust Backend - Concurrent Actor Architecture");
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    
-    // Create actor pool with 8 workers
-    let pool = Arc::new(ActorPoolManager::new(8));
-    
+
+    // Create actor pool with 8 workers sharing one bb8 pool (or the sled
+    // store), checking out a connection per request instead of going through
+    // one shared, mutex-guarded client.
+    let pool = Arc::new(
+        ActorPoolManager::new(8)
+            .await
+            .expect("Failed to initialize actor pool"),
+    );
+
+    // Session registry backs both the WebSocket endpoint and the NOTIFY
+    // subscriber below, so a note committed by any process reaches every
+    // session registered to its room.
+    let registry = SessionRegistry::new().start();
+
+    // LISTEN/NOTIFY is a Postgres feature; the embedded sled backend has no
+    // cluster to fan notes out across, so there's nothing to subscribe to.
+    if matches!(config::store_backend_from_env(), config::StoreBackend::Postgres) {
+        let (channel, room_filter) = config::notify_config_from_env();
+        notify::NotifyActor::new(channel, room_filter, registry.clone().recipient()).start();
+    }
+
     println!("✓ Actor pool initialized with {} workers", pool.note_processors.len());
     println!("✓ Starting HTTP server on 0.0.0.0:8003...");
-    
+
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(Arc::clone(&pool)))
+            .app_data(web::Data::new(registry.clone()))
             .route("/api/rust/process-note-concurrent", web::post().to(process_note_concurrent))
             .route("/api/rust/save-song", web::post().to(save_song))
+            .route("/api/rust/user-notes", web::get().to(get_user_notes))
             .route("/api/rust/health", web::get().to(health_check))
+            .route("/api/rust/ws", web::get().to(ws_index))
     })
     .bind(("0.0.0.0", 8003))?
     .run()